@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
-use labello::{Config, Encoder, EncoderType};
+use labello::{Config, Encoder, EncoderType, Ordering, UnknownPolicy};
 
 
 fn labello_bench(c: &mut Criterion) {
@@ -57,7 +57,9 @@ fn labello_bench(c: &mut Criterion) {
 
             let config = Config{
                 max_nclasses: Some(3),
-                mapping_function: None
+                mapping_function: None,
+                unknown: UnknownPolicy::Error,
+                ordering: Ordering::InsertionOrder,
             };
 
             // transform original data to internal encoded representation
@@ -15,14 +15,59 @@ use std::hash::Hash;
 use std::cmp::Eq;
 use std::fmt::Debug;
 use std::iter::Iterator;
+use std::str::FromStr;
 
 /// configuration for encoder (metadata)
+///
+/// `V` is the value type a `Custom` encoder's `mapping_function` produces;
+/// it defaults to `u64` so `Ordinal`/`OneHot` configs (which never read
+/// `mapping_function`) don't need to name it.
 #[derive(Debug, Clone)]
-pub struct Config<T> {
+pub struct Config<T, V = u64> {
     // maximum number of classes (repeat after max)
     pub max_nclasses: Option<u64>,
     // only for custom encoder (define closure and apply to the single element)
-    pub mapping_function: Option<fn(T) -> u64>,
+    pub mapping_function: Option<fn(T) -> V>,
+    // what to do with a category at transform time that was not seen during fit
+    pub unknown: UnknownPolicy<V>,
+    // order in which newly seen categories in a batch are assigned contiguous codes
+    pub ordering: Ordering,
+}
+
+/// What `transform`/`transform_custom` should do with a value that was not
+/// seen during `fit`. `V` is the sentinel's type, matching `Config`'s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnknownPolicy<V = u64> {
+    /// fail the whole transform with `EncodeError::UnknownCategory`, naming the offending value
+    Error,
+    /// drop the value from the output, same as labello's original (undocumented) behavior
+    Ignore,
+    /// map the value to this reserved sentinel, or an all-false vector for `OneHot`
+    Encode(V),
+}
+
+/// Order in which the categories first seen in a `fit`/`partial_fit` batch
+/// are assigned contiguous codes, so that two processes fitting the same
+/// data always produce the same encoding instead of depending on
+/// `HashMap` iteration order.
+///
+/// Codes already assigned to previously seen categories are never
+/// reassigned (see [`Encoder::partial_fit`]) — `ordering` only decides the
+/// order in which *new* categories in the current batch are numbered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ordering {
+    /// first-seen order within the batch (the documented default)
+    InsertionOrder,
+    /// sort new categories with `Ord`
+    Lexicographic,
+    /// assign the lowest code to the most frequent new category, breaking ties lexicographically
+    Frequency,
+}
+
+impl Default for Ordering {
+    fn default() -> Self {
+        Ordering::InsertionOrder
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -35,27 +80,43 @@ pub enum EncoderType {
     CustomMapping,
 }
 
+/// `Ordinal` and `OneHot` stay specialized to `u64` codes; `Custom` is
+/// generic over its output type `V` (see `Config`) so a custom mapping
+/// function can target signed ints, `String`, or any other
+/// `Hash + Eq + Clone + Debug` type without a lossy cast into `u64`.
 #[derive(Debug)]
-pub enum Encoder<T>
+pub enum Encoder<T, V = u64>
 where T: Hash + Eq + Debug
 {
-    Ordinal(HashMap<T, u64>),
-    OneHot(HashMap<T, OheRepr>),
-    Custom(HashMap<T, u64>)
+    // second field is a reverse index (code -> originals) rebuilt at the
+    // end of every `fit`/`partial_fit` call so `inverse_transform` can do
+    // O(1) lookups instead of scanning the forward map; see
+    // `build_reverse_index`
+    Ordinal(HashMap<T, u64>, HashMap<u64, Vec<T>>),
+    // stores the stable ordinal index assigned to each category; the
+    // one-hot bit-vector representation is derived on demand (see
+    // `ohe_repr`) so it never needs to be recomputed for the whole map
+    // when a new category arrives. The reverse index is keyed on that
+    // derived bit pattern, since it (unlike the code) is what
+    // `inverse_transform` is actually given back
+    OneHot(HashMap<T, u64>, HashMap<OheRepr, Vec<T>>),
+    Custom(HashMap<T, V>, HashMap<V, Vec<T>>)
 }
 
 type OheRepr = Vec<bool>;
 
 /// transformed data type
 ///
+/// `V` is only carried by `CustomMapping`; `Ordinal` and `OneHot` stay
+/// `u64`-coded regardless of it, so most call sites never name it.
 #[derive(Debug, Clone)]
-pub enum Transform {
+pub enum Transform<V = u64> {
     Ordinal(Vec<u64>),
     OneHot(Vec<OheRepr>),
-    CustomMapping(Vec<u64>)
+    CustomMapping(Vec<V>)
 }
 
-impl Transform {
+impl<V> Transform<V> {
     pub fn len(&self) -> usize {
         match self {
             Transform::Ordinal(data) => data.len(),
@@ -65,74 +126,296 @@ impl Transform {
     }
 }
 
-impl <T> Encoder<T>
-where T: Hash + Eq + Clone + Debug
+/// Errors that can occur while transforming data with `UnknownPolicy::Error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError<T> {
+    /// `data` contained a category not seen during `fit`
+    UnknownCategory(T),
+}
+
+/// Errors that can occur while decoding a persisted encoder from bytes.
+///
+/// A tagged binary value produced by [`Encoder::encode`] is always
+/// self-describing, so decoding only fails when the bytes are truncated,
+/// corrupt, or simply not a labello encoder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// the input ended before the expected number of bytes could be read
+    UnexpectedEof,
+    /// the leading variant tag was not `"ordinal"`, `"onehot"` or `"custom"`
+    UnknownTag,
+    /// a length prefix was not valid ascii decimal
+    InvalidLength,
+    /// a text field was not valid utf-8
+    InvalidUtf8,
+    /// a record delimiter (`{`, `}`, `,`) or a type discriminator (`t`, `n`)
+    /// was not where expected
+    Malformed,
+    /// a key or value could not be parsed back into its type (`T` or `V` via
+    /// `FromStr`, or a `u64` code)
+    InvalidValue,
+}
+
+/// A cursor over the tagged binary format read by [`Encoder::decode`].
+///
+/// Every scalar is prefixed with a one-byte type discriminator and an
+/// ascii-decimal byte length (`t<len>:<utf8>,` for text, `n<len>:<u64>,`
+/// for numerics), so the cursor always knows exactly how many bytes to
+/// consume next.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, pos: 0 }
+    }
+
+    fn peek(&self) -> Result<u8, DecodeError> {
+        self.bytes.get(self.pos).copied().ok_or(DecodeError::UnexpectedEof)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self.pos.checked_add(n).ok_or(DecodeError::UnexpectedEof)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(DecodeError::UnexpectedEof)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), DecodeError> {
+        if self.take(1)?[0] == b {
+            Ok(())
+        } else {
+            Err(DecodeError::Malformed)
+        }
+    }
+
+    fn take_len_until(&mut self, delim: u8) -> Result<usize, DecodeError> {
+        let start = self.pos;
+        while self.peek()? != delim {
+            self.pos += 1;
+        }
+        let digits = &self.bytes[start..self.pos];
+        self.pos += 1;
+        std::str::from_utf8(digits)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or(DecodeError::InvalidLength)
+    }
+
+    fn read_text(&mut self) -> Result<String, DecodeError> {
+        self.expect(b't')?;
+        let len = self.take_len_until(b':')?;
+        let bytes = self.take(len)?;
+        self.expect(b',')?;
+        std::str::from_utf8(bytes).map(str::to_string).map_err(|_| DecodeError::InvalidUtf8)
+    }
+
+    fn read_numeric(&mut self) -> Result<u64, DecodeError> {
+        self.expect(b'n')?;
+        let len = self.take_len_until(b':')?;
+        let bytes = self.take(len)?;
+        self.expect(b',')?;
+        std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or(DecodeError::InvalidValue)
+    }
+
+    /// Read a `{<count>:<pairs>}` record, calling `read_pair` once per entry.
+    fn read_record<V>(
+        &mut self,
+        mut read_pair: impl FnMut(&mut Self) -> Result<(String, V), DecodeError>,
+    ) -> Result<Vec<(String, V)>, DecodeError> {
+        self.expect(b'{')?;
+        let count = self.take_len_until(b':')?;
+        // count comes straight from the input and each pair consumes at least one
+        // byte, so cap the pre-allocation at what's left to avoid an attacker-chosen
+        // count aborting the process via Vec::with_capacity's allocation failure.
+        let mut pairs = Vec::with_capacity(count.min(self.bytes.len() - self.pos));
+        for _ in 0..count {
+            pairs.push(read_pair(self)?);
+        }
+        self.expect(b'}')?;
+        Ok(pairs)
+    }
+}
+
+fn write_text(out: &mut Vec<u8>, s: &str) {
+    out.push(b't');
+    out.extend_from_slice(s.len().to_string().as_bytes());
+    out.push(b':');
+    out.extend_from_slice(s.as_bytes());
+    out.push(b',');
+}
+
+fn write_numeric(out: &mut Vec<u8>, n: u64) {
+    let digits = n.to_string();
+    out.push(b'n');
+    out.extend_from_slice(digits.len().to_string().as_bytes());
+    out.push(b':');
+    out.extend_from_slice(digits.as_bytes());
+    out.push(b',');
+}
+
+fn write_record_header(out: &mut Vec<u8>, count: usize) {
+    out.push(b'{');
+    out.extend_from_slice(count.to_string().as_bytes());
+    out.push(b':');
+}
+
+/// Assign a stable ordinal index to each new category in `data`, leaving
+/// categories already present in `map` untouched. Shared by the `Ordinal`
+/// and `OneHot` encoders, whose fitted state is a plain `HashMap<T, u64>`
+/// of assigned indices; `OneHot` derives its bit-vector representation
+/// from this map on demand (see [`ohe_repr`]) instead of storing it.
+///
+/// Resuming from `map`'s current maximum (rather than restarting at zero)
+/// is what lets `fit`/`partial_fit` be called many times over successive
+/// batches without earlier codes shifting, and makes `max_nclasses` act
+/// cumulatively across those calls. `ordering` only governs the order in
+/// which the *new* categories found in this batch are numbered, so the
+/// result no longer depends on `HashMap` iteration order.
+fn assign_stable_indices<T: Hash + Eq + Clone + Ord>(map: &mut HashMap<T, u64>, data: &Vec<T>, max_nclasses: u64, ordering: &Ordering) {
+    let mut current_idx = match map.values().max() {
+        Some(&m) if m < max_nclasses => m + 1,
+        Some(&m) => m,
+        None => 0,
+    };
+
+    match ordering {
+        Ordering::InsertionOrder => {
+            for el in data.iter() {
+                if !map.contains_key(el) {
+                    map.insert(el.clone(), current_idx);
+                    if current_idx < max_nclasses {
+                        current_idx += 1;
+                    }
+                }
+            }
+        },
+
+        Ordering::Lexicographic | Ordering::Frequency => {
+            // count occurrences of each new category, then assign indices
+            // over an explicitly sorted Vec rather than relying on HashMap
+            // iteration order
+            let mut counts: HashMap<T, u64> = HashMap::new();
+            for el in data.iter() {
+                if !map.contains_key(el) {
+                    *counts.entry(el.clone()).or_insert(0) += 1;
+                }
+            }
+            let mut sorted: Vec<(T, u64)> = counts.into_iter().collect();
+            sorted.sort_by(|(key_a, count_a), (key_b, count_b)| match ordering {
+                Ordering::Frequency => count_b.cmp(count_a).then_with(|| key_a.cmp(key_b)),
+                _ => key_a.cmp(key_b),
+            });
+            for (key, _count) in sorted {
+                map.insert(key, current_idx);
+                if current_idx < max_nclasses {
+                    current_idx += 1;
+                }
+            }
+        },
+    }
+}
+
+/// Build a reverse index (code -> originals) from a fitted forward map.
+/// `K` is the code type: `u64` for `Ordinal`, or the custom encoder's own
+/// output type `V`.
+///
+/// A code may map back to more than one original category whenever the
+/// forward map is non-injective (`max_nclasses` collapsing distinct
+/// categories onto the same code, or a non-injective custom
+/// `mapping_function`), so every code stores a `Vec<T>` rather than a
+/// single `T`. Among those, the one `inverse_transform` returns is
+/// whichever this function happens to visit first while iterating `map`;
+/// callers relying on a specific original surviving a collision should not
+/// depend on that order.
+fn build_reverse_index<T: Hash + Eq + Clone, K: Hash + Eq + Clone>(map: &HashMap<T, K>) -> HashMap<K, Vec<T>> {
+    let mut reverse: HashMap<K, Vec<T>> = HashMap::new();
+    for (key, code) in map.iter() {
+        reverse.entry(code.clone()).or_default().push(key.clone());
+    }
+    reverse
+}
+
+/// Same as [`build_reverse_index`], but keyed on the one-hot bit pattern
+/// (`ohe_repr(code, vecsize)`) rather than the raw code, since that bit
+/// pattern is what `OneHot::inverse_transform` is actually given back.
+fn build_ohe_reverse_index<T: Hash + Eq + Clone>(map: &HashMap<T, u64>, vecsize: usize) -> HashMap<OheRepr, Vec<T>> {
+    let mut reverse: HashMap<OheRepr, Vec<T>> = HashMap::new();
+    for (key, code) in map.iter() {
+        reverse.entry(ohe_repr(*code, vecsize)).or_default().push(key.clone());
+    }
+    reverse
+}
+
+/// Derive the one-hot bit-vector representation of a category's stable
+/// ordinal index on demand: `vecsize` bits with only bit `value` set, so
+/// distinct codes never share a pattern and none equals the all-false
+/// vector. A `value` of `vecsize` or more yields the all-false vector.
+fn ohe_repr(value: u64, vecsize: usize) -> OheRepr {
+    let mut converted: OheRepr = vec![false; vecsize];
+    if value < vecsize as u64 {
+        converted[value as usize] = true;
+    }
+    converted
+}
+
+impl <T, V> Encoder<T, V>
+where T: Hash + Eq + Clone + Debug,
+      V: Hash + Eq + Clone + Debug,
 {
-    pub fn new(enctype: Option<EncoderType>) -> Encoder<T> {
+    pub fn new(enctype: Option<EncoderType>) -> Encoder<T, V> {
         let enctype = enctype.unwrap_or(EncoderType::Ordinal);
 
         match enctype {
-            EncoderType::Ordinal => Encoder::Ordinal(HashMap::new()),
-            EncoderType::OneHot => Encoder::OneHot(HashMap::new()),
-            EncoderType::CustomMapping => Encoder::Custom(HashMap::new())
+            EncoderType::Ordinal => Encoder::Ordinal(HashMap::new(), HashMap::new()),
+            EncoderType::OneHot => Encoder::OneHot(HashMap::new(), HashMap::new()),
+            EncoderType::CustomMapping => Encoder::Custom(HashMap::new(), HashMap::new())
         }
     }
 
     /// Fit label encoder given the type (ordinal, one-hot, custom)
     ///
-    pub fn fit(&mut self, data: &Vec<T>, config: &Config<T>) {
+    /// Calling `fit` again on an already-fitted encoder behaves like
+    /// [`Encoder::partial_fit`]: categories seen in an earlier call keep
+    /// their original code, and new categories continue numbering from the
+    /// current maximum.
+    ///
+    /// The reverse index used by `inverse_transform` is rebuilt from the
+    /// forward map at the end of this call, so it always reflects the
+    /// latest fitted state (for `OneHot`, whose bit-vector width grows with
+    /// `nclasses`, this is also why it cannot simply be updated
+    /// incrementally).
+    ///
+    /// `T: Ord` is required here rather than on the whole `impl` block so
+    /// that `new`, `transform`, `inverse_transform` and `nclasses` stay
+    /// available for key types that aren't `Ord`. `fit` itself still needs
+    /// the bound even for a `Custom` encoder, because `Ordinal`/`OneHot`
+    /// share this one method and delegate to `assign_stable_indices` (which
+    /// sorts new categories for `Ordering::Lexicographic`/`Frequency`) —
+    /// Rust requires a generic function's bounds to cover every branch of
+    /// its body, not just the one a given call happens to take. A `Custom`
+    /// encoder over a non-`Ord` key should use [`Encoder::fit_custom`]
+    /// instead, which never sorts and so never needs the bound.
+    pub fn fit(&mut self, data: &Vec<T>, config: &Config<T, V>) where T: Ord {
         let max_nclasses = config.max_nclasses.unwrap_or(u64::MAX) - 1;
 
         match self {
-            Encoder::Ordinal(map) => {
-                let mut current_idx = 0u64;
-                for el in data.iter() {
-                    if !map.contains_key(el) {
-                        map.insert(el.clone(), current_idx);
-                        if current_idx < max_nclasses {
-                            current_idx += 1;
-                        }
-                    }
-                }
+            Encoder::Ordinal(map, reverse) => {
+                assign_stable_indices(map, data, max_nclasses, &config.ordering);
+                *reverse = build_reverse_index(map);
             },
 
-            Encoder::OneHot(map) => {
-                let mut mapping: HashMap<T, u64> = HashMap::new();
-                let mut current_idx = 0u64;
-                // encode in a temporary hashmap (mapping)
-                for el in data.iter() {
-                    if !mapping.contains_key(el) {
-                        mapping.insert(el.clone(), current_idx);
-                        if current_idx < max_nclasses {
-                            current_idx += 1;
-                        }
-                    }
-                }
-
-                let vecsize = mapping.len();
-                for (key, value) in mapping.into_iter() {
-                    let mut converted: OheRepr = format!("{:b}", value)
-                                                .chars()
-                                                .enumerate()
-                                                .filter_map(|(_i, n)| match n {
-                                                    '1' => {
-                                                        Some(true)
-                                                    },
-
-                                                    '0' => Some(false),
-                                                    _ => panic!("Invalid conversion to binary"),
-                                                })
-                                                .collect();
-                    // push remaining zeros (vecsize - current len)
-                    for _ in 0..vecsize - converted.len() {
-                        converted.push(false);
-                    }
-                    // insert into final hashmap
-                    map.insert(key, converted);
-                }
+            Encoder::OneHot(map, reverse) => {
+                assign_stable_indices(map, data, max_nclasses, &config.ordering);
+                *reverse = build_ohe_reverse_index(map, map.len());
             },
 
-            Encoder::Custom(map) => {
+            Encoder::Custom(map, reverse) => {
                 let mapping_func = config.mapping_function.unwrap();
                 for el in data.iter() {
                     if !map.contains_key(el) {
@@ -140,87 +423,113 @@ where T: Hash + Eq + Clone + Debug
                         map.insert(el.clone(), value);
                     }
                 }
+                *reverse = build_reverse_index(map);
             },
         }
     }
 
-    /// Transform data to normalized encoding
+    /// Update the learned mapping with another batch of data without
+    /// discarding codes already assigned to previously seen categories.
     ///
-    pub fn transform(&self, data: &Vec<T>) -> Transform  {
-        match self {
-            Encoder::Ordinal(map) => {
-                let res: Vec<u64> = data.iter().filter_map(|el| map.get(el)).cloned().collect();
-                Transform::Ordinal(res)
-            }
+    /// Safe to call repeatedly over many arbitrarily-sized batches: a
+    /// category's code, once assigned, never changes, new categories
+    /// continue numbering from the current maximum, and `max_nclasses` is
+    /// enforced cumulatively across all calls rather than per-batch.
+    pub fn partial_fit(&mut self, batch: &Vec<T>, config: &Config<T, V>) where T: Ord {
+        self.fit(batch, config)
+    }
 
-            Encoder::OneHot(map) => {
-                let res: Vec<OheRepr> = data.iter().filter_map(|el| map.get(el)).cloned().collect();
-                Transform::OneHot(res)
+    /// Same as [`Encoder::fit`], but only for a `Custom` encoder, and
+    /// without requiring `T: Ord`.
+    ///
+    /// `Custom` never sorts its categories (`mapping_function` decides each
+    /// code directly), so unlike `fit` this has no need of an ordering over
+    /// `T` at all; use this instead of `fit` when `T` isn't `Ord`. Panics if
+    /// `self` isn't `Encoder::Custom`.
+    pub fn fit_custom(&mut self, data: &Vec<T>, config: &Config<T, V>) {
+        match self {
+            Encoder::Custom(map, reverse) => {
+                let mapping_func = config.mapping_function.unwrap();
+                for el in data.iter() {
+                    if !map.contains_key(el) {
+                        let value = mapping_func(el.clone());
+                        map.insert(el.clone(), value);
+                    }
+                }
+                *reverse = build_reverse_index(map);
             },
+            _ => panic!("fit_custom called on a non-Custom encoder"),
+        }
+    }
 
-            Encoder::Custom(map) => {
-                let res: Vec<u64> = data.iter().filter_map(|el| map.get(el)).cloned().collect();
-                Transform::CustomMapping(res)
-            },
+    /// Same as [`Encoder::partial_fit`], but for [`Encoder::fit_custom`]:
+    /// only for a `Custom` encoder, and without requiring `T: Ord`.
+    pub fn partial_fit_custom(&mut self, batch: &Vec<T>, config: &Config<T, V>) {
+        self.fit_custom(batch, config)
+    }
 
+    /// Same as [`Encoder::transform`], but only for a `Custom` encoder, for
+    /// any `V` rather than just the default `u64`: `Encode(sentinel)` pushes
+    /// the sentinel as the caller-supplied `V` directly, with no `u64` to
+    /// convert. Panics if `self` isn't `Encoder::Custom`.
+    pub fn transform_custom(&self, data: &Vec<T>, config: &Config<T, V>) -> Result<Transform<V>, EncodeError<T>> {
+        match self {
+            Encoder::Custom(map, _) => {
+                let mut res = Vec::with_capacity(data.len());
+                for el in data.iter() {
+                    match map.get(el) {
+                        Some(code) => res.push(code.clone()),
+                        None => match &config.unknown {
+                            UnknownPolicy::Error => return Err(EncodeError::UnknownCategory(el.clone())),
+                            UnknownPolicy::Ignore => {},
+                            UnknownPolicy::Encode(sentinel) => res.push(sentinel.clone()),
+                        },
+                    }
+                }
+                Ok(Transform::CustomMapping(res))
+            },
+            _ => panic!("transform_custom called on a non-Custom encoder"),
         }
-
     }
 
-    /// Transforms labels back to the original data (not necessarily true with custom encoder)
+    /// Transforms labels back to the original data (not necessarily true with custom encoder).
     ///
-    pub fn inverse_transform(&self, data: &Transform) -> Vec<T> {
+    /// Looks each code up in the reverse index built at the end of the last
+    /// `fit`/`partial_fit` call (see `build_reverse_index`), so this is O(1)
+    /// per element rather than scanning the whole forward map. The result
+    /// always has the same length as `data`, one entry per code; if a code
+    /// collides across categories (see `build_reverse_index`), the first
+    /// original stored for it is returned. A code can also be absent from
+    /// the reverse index entirely — e.g. a sentinel introduced by
+    /// `UnknownPolicy::Encode` during `transform`, which was never a key in
+    /// the fitted forward map — in which case the corresponding entry is
+    /// `None` rather than a lookup panic.
+    pub fn inverse_transform(&self, data: &Transform<V>) -> Vec<Option<T>> {
         match self {
-            Encoder::Ordinal(mapping) => match data {
-                Transform::Ordinal(typed_data) => {
-                    let result: Vec<T> = typed_data.iter()
-                    .flat_map(|&el| {
-                        mapping.into_iter()
-                        .filter(move |&(_key, val)| val == &el)
-                        .map(|(key, &_val)| key.clone())
-                    })
-                    .collect();
-                    result
-                },
+            Encoder::Ordinal(_, reverse) => match data {
+                Transform::Ordinal(typed_data) => typed_data.iter()
+                    .map(|code| reverse.get(code)
+                        .and_then(|originals| originals.first())
+                        .cloned())
+                    .collect(),
                 _ => panic!("Transformed data not compatible with this encoder"),
             },
 
-            // TODO WIP inverse mapping is not reversible for one-hot (ERROR!!)
-            Encoder::OneHot(mapping) => match data {
-                Transform::OneHot(typed_data) => {
-                    let result: Vec<T> = typed_data.iter()
-                    .flat_map(|el| {
-                        mapping.into_iter()
-                        .filter(move |&(_key, val)| {
-                            let mut equal_el: usize = 0;
-                            for i in 0..val.len() {
-                                if val[i] == el[i] {
-                                    equal_el += 1;
-                                }
-                            }
-                            // val == el
-                            equal_el == val.len()
-                        }
-                    )
-                        .map(|(key, _val)| key.clone())
-                    })
-                    .collect();
-                    result
-                },
+            Encoder::OneHot(_, reverse) => match data {
+                Transform::OneHot(typed_data) => typed_data.iter()
+                    .map(|bits| reverse.get(bits)
+                        .and_then(|originals| originals.first())
+                        .cloned())
+                    .collect(),
                 _ => panic!("Transformed data not compatible with this encoder")
             },
 
-            Encoder::Custom(mapping) => match data {
-                Transform::CustomMapping(typed_data) => {
-                    let result = typed_data.into_iter().flat_map(|&el| {
-                        mapping
-                            .into_iter()
-                            .filter(move |&(_k, v)| v == &el)
-                        .map(|(k, &_v)| k.clone())
-                    })
-                    .collect();
-                    result
-                },
+            Encoder::Custom(_, reverse) => match data {
+                Transform::CustomMapping(typed_data) => typed_data.iter()
+                    .map(|code| reverse.get(code)
+                        .and_then(|originals| originals.first())
+                        .cloned())
+                    .collect(),
                 _ => panic!("Transformed data not compatible with this encoder"),
             }
         }
@@ -231,7 +540,7 @@ where T: Hash + Eq + Clone + Debug
     pub fn nclasses(&self) -> usize {
         match self {
             // TODO len is the same for every type
-            Encoder::Ordinal(mapping) => {
+            Encoder::Ordinal(mapping, _) => {
                 let values: Vec<u64> = mapping.values().cloned().collect();
                 let len = values.iter().max();
                 match len {
@@ -239,8 +548,170 @@ where T: Hash + Eq + Clone + Debug
                     _ => 0 as usize
                 }
             },
-            Encoder::OneHot(map) => map.len(),
-            Encoder::Custom(map) => map.len(),
+            Encoder::OneHot(map, _) => map.len(),
+            Encoder::Custom(map, _) => map.len(),
+        }
+    }
+}
+
+impl <T> Encoder<T, u64>
+where T: Hash + Eq + Clone + Debug,
+{
+    /// Transform data to normalized encoding.
+    ///
+    /// `config.unknown` decides what happens when `data` contains a
+    /// category that was not seen during `fit`: `Error` fails the whole
+    /// call naming the offending value, `Ignore` drops it from the output
+    /// (so the result may be shorter than `data`), and `Encode(sentinel)`
+    /// maps it to the given sentinel code (an all-false vector for
+    /// `OneHot`). Only defined for the default `V = u64`; a `Custom`
+    /// encoder with another `V` has no `u64` sentinel to fall back to, so
+    /// use [`Encoder::transform_custom`] for it instead.
+    pub fn transform(&self, data: &Vec<T>, config: &Config<T, u64>) -> Result<Transform<u64>, EncodeError<T>> {
+        match self {
+            Encoder::Ordinal(map, _) => {
+                let mut res = Vec::with_capacity(data.len());
+                for el in data.iter() {
+                    match map.get(el) {
+                        Some(code) => res.push(*code),
+                        None => match config.unknown {
+                            UnknownPolicy::Error => return Err(EncodeError::UnknownCategory(el.clone())),
+                            UnknownPolicy::Ignore => {},
+                            UnknownPolicy::Encode(sentinel) => res.push(sentinel),
+                        },
+                    }
+                }
+                Ok(Transform::Ordinal(res))
+            }
+
+            Encoder::OneHot(map, _) => {
+                let vecsize = map.len();
+                let mut res = Vec::with_capacity(data.len());
+                for el in data.iter() {
+                    match map.get(el) {
+                        Some(code) => res.push(ohe_repr(*code, vecsize)),
+                        None => match config.unknown {
+                            UnknownPolicy::Error => return Err(EncodeError::UnknownCategory(el.clone())),
+                            UnknownPolicy::Ignore => {},
+                            UnknownPolicy::Encode(_) => res.push(vec![false; vecsize]),
+                        },
+                    }
+                }
+                Ok(Transform::OneHot(res))
+            },
+
+            Encoder::Custom(map, _) => {
+                let mut res = Vec::with_capacity(data.len());
+                for el in data.iter() {
+                    match map.get(el) {
+                        Some(code) => res.push(*code),
+                        None => match config.unknown {
+                            UnknownPolicy::Error => return Err(EncodeError::UnknownCategory(el.clone())),
+                            UnknownPolicy::Ignore => {},
+                            UnknownPolicy::Encode(sentinel) => res.push(sentinel),
+                        },
+                    }
+                }
+                Ok(Transform::CustomMapping(res))
+            },
+        }
+    }
+}
+
+impl <T, V> Encoder<T, V>
+where T: Hash + Eq + Clone + Debug + ToString + FromStr,
+      V: Hash + Eq + Clone + Debug + ToString + FromStr,
+{
+    /// Serialize a fitted encoder into a self-describing tagged binary format
+    /// so it can be persisted and reloaded without re-fitting.
+    ///
+    /// The layout borrows a netstring-like convention: a top-level tag names
+    /// the variant (`"ordinal"`, `"onehot"`, `"custom"`) followed by a
+    /// length-prefixed record of key/value pairs, where every scalar carries
+    /// its own type discriminator and byte length. A `Custom` encoder only
+    /// persists the materialized `HashMap<T, V>`; the mapping closure lives
+    /// on `Config`, not on the encoder, so there is nothing else to save.
+    /// `Ordinal`/`OneHot` codes are always written as `n`-tagged numerics;
+    /// `Custom` values are `V::to_string()`, written as `t`-tagged text like
+    /// the keys, since `V` is not necessarily numeric.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            Encoder::Ordinal(map, _) => {
+                out.extend_from_slice(b"ordinal");
+                write_record_header(&mut out, map.len());
+                for (key, value) in map.iter() {
+                    write_text(&mut out, &key.to_string());
+                    write_numeric(&mut out, *value);
+                }
+                out.push(b'}');
+            },
+            Encoder::OneHot(map, _) => {
+                out.extend_from_slice(b"onehot");
+                write_record_header(&mut out, map.len());
+                for (key, value) in map.iter() {
+                    write_text(&mut out, &key.to_string());
+                    write_numeric(&mut out, *value);
+                }
+                out.push(b'}');
+            },
+            Encoder::Custom(map, _) => {
+                out.extend_from_slice(b"custom");
+                write_record_header(&mut out, map.len());
+                for (key, value) in map.iter() {
+                    write_text(&mut out, &key.to_string());
+                    write_text(&mut out, &value.to_string());
+                }
+                out.push(b'}');
+            },
+        }
+        out
+    }
+
+    /// Reconstruct a fitted encoder from bytes produced by [`Encoder::encode`].
+    ///
+    /// Duplicate keys within the record resolve last-wins, the same as a
+    /// plain `HashMap::insert` loop would. As with `encode`, a decoded
+    /// `Custom` encoder only restores the materialized mapping; callers must
+    /// still supply `mapping_function` on `Config` if they plan to `fit`
+    /// further batches. The reverse index used by `inverse_transform` is not
+    /// part of the wire format — it is cheap to derive, so it is rebuilt
+    /// from the decoded forward map instead.
+    pub fn decode(bytes: &[u8]) -> Result<Encoder<T, V>, DecodeError> {
+        let mut r = Reader::new(bytes);
+        if bytes.starts_with(b"ordinal") {
+            r.pos = "ordinal".len();
+            let pairs = r.read_record(|r| Ok((r.read_text()?, r.read_numeric()?)))?;
+            let mut map = HashMap::new();
+            for (key, value) in pairs {
+                let key = T::from_str(&key).map_err(|_| DecodeError::InvalidValue)?;
+                map.insert(key, value);
+            }
+            let reverse = build_reverse_index(&map);
+            Ok(Encoder::Ordinal(map, reverse))
+        } else if bytes.starts_with(b"onehot") {
+            r.pos = "onehot".len();
+            let pairs = r.read_record(|r| Ok((r.read_text()?, r.read_numeric()?)))?;
+            let mut map = HashMap::new();
+            for (key, value) in pairs {
+                let key = T::from_str(&key).map_err(|_| DecodeError::InvalidValue)?;
+                map.insert(key, value);
+            }
+            let reverse = build_ohe_reverse_index(&map, map.len());
+            Ok(Encoder::OneHot(map, reverse))
+        } else if bytes.starts_with(b"custom") {
+            r.pos = "custom".len();
+            let pairs = r.read_record(|r| Ok((r.read_text()?, r.read_text()?)))?;
+            let mut map = HashMap::new();
+            for (key, value) in pairs {
+                let key = T::from_str(&key).map_err(|_| DecodeError::InvalidValue)?;
+                let value = V::from_str(&value).map_err(|_| DecodeError::InvalidValue)?;
+                map.insert(key, value);
+            }
+            let reverse = build_reverse_index(&map);
+            Ok(Encoder::Custom(map, reverse))
+        } else {
+            Err(DecodeError::UnknownTag)
         }
     }
 }
@@ -250,6 +721,27 @@ where T: Hash + Eq + Clone + Debug
 mod tests {
     use super::*;
 
+    // Hash + Eq + Clone + Debug, but deliberately not Ord: a Custom encoder
+    // must stay usable over keys like this (see
+    // `test_fit_custom_encoder_with_non_ord_key`).
+    #[derive(Hash, Eq, PartialEq, Clone, Debug)]
+    struct NonOrdKey(String);
+
+    #[test]
+    fn test_fit_custom_encoder_with_non_ord_key() {
+        let data = vec![NonOrdKey("hello".to_string()), NonOrdKey("world".to_string())];
+        let config: Config<NonOrdKey> = Config {
+            max_nclasses: None,
+            mapping_function: Some(|el: NonOrdKey| el.0.len() as u64),
+            unknown: UnknownPolicy::Error,
+            ordering: Ordering::InsertionOrder,
+        };
+        let mut enc: Encoder<NonOrdKey> = Encoder::new(Some(EncoderType::CustomMapping));
+        enc.fit_custom(&data, &config);
+
+        assert_eq!(enc.nclasses(), 2);
+    }
+
     #[test]
     fn test_one_hot_encoding() {
         let x = 128u64;
@@ -284,7 +776,9 @@ mod tests {
         let enctype = EncoderType::Ordinal;
         let config = Config{
             max_nclasses: None,
-            mapping_function: None
+            mapping_function: None,
+            unknown: UnknownPolicy::Error,
+            ordering: Ordering::InsertionOrder,
         };
         let mut enc: Encoder<String> = Encoder::new(Some(enctype));
         dbg!("created encoder ", &enc);
@@ -292,7 +786,7 @@ mod tests {
         enc.fit(&data, &config);
         dbg!("fitted encoder:", &enc);
 
-        let trans_data = enc.transform(&data);
+        let trans_data = enc.transform(&data, &config).expect("no unseen categories");
         dbg!("trans data: ", &trans_data);
 
         let recon_data = enc.inverse_transform(&trans_data);
@@ -316,7 +810,9 @@ mod tests {
         let enctype = EncoderType::Ordinal;
         let config = Config{
             max_nclasses: Some(3),
-            mapping_function: None
+            mapping_function: None,
+            unknown: UnknownPolicy::Error,
+            ordering: Ordering::InsertionOrder,
         };
         let mut enc: Encoder<String> = Encoder::new(Some(enctype));
         dbg!("created encoder ", &enc);
@@ -342,13 +838,15 @@ mod tests {
 
         let config = Config {
             max_nclasses: Some(10),
-            mapping_function: None
+            mapping_function: None,
+            unknown: UnknownPolicy::Error,
+            ordering: Ordering::InsertionOrder,
         };
         let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::OneHot));
         enc.fit(&data, &config);
         dbg!("fitted encoder: ", &enc);
 
-        let trans_data = enc.transform(&data);
+        let trans_data = enc.transform(&data, &config).expect("no unseen categories");
         dbg!("trans data: ", &trans_data);
         assert_eq!(trans_data.len(), data.len());
 
@@ -376,16 +874,352 @@ mod tests {
                 "goodbye" => 99,
                 _ => 0
             }),
+            unknown: UnknownPolicy::Error,
+            ordering: Ordering::InsertionOrder,
         };
 
         let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::CustomMapping));
         enc.fit(&data, &config);
         dbg!("fitted encoder: ", &enc);
 
-        let trans_data = enc.transform(&data);
+        let trans_data = enc.transform(&data, &config).expect("no unseen categories");
         dbg!("trans data: ", &trans_data);
 
         let recon_data = enc.inverse_transform(&trans_data);
         dbg!("recon data:", &recon_data);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_fit_custom_encoder_with_non_u64_value_type() {
+        let data: Vec<String> = vec!["hello".to_string(), "world".to_string(), "again".to_string()];
+        let config: Config<String, i64> = Config {
+            max_nclasses: None,
+            mapping_function: Some(|el| match el.as_str() {
+                "hello" => -1,
+                "world" => -2,
+                _ => -3,
+            }),
+            unknown: UnknownPolicy::Error,
+            ordering: Ordering::InsertionOrder,
+        };
+
+        let mut enc: Encoder<String, i64> = Encoder::new(Some(EncoderType::CustomMapping));
+        enc.fit(&data, &config);
+
+        let trans_data = enc.transform_custom(&data, &config).expect("no unseen categories");
+        match &trans_data {
+            Transform::CustomMapping(values) => assert_eq!(values, &vec![-1, -2, -3]),
+            _ => unreachable!(),
+        }
+
+        let recon_data = enc.inverse_transform(&trans_data);
+        assert_eq!(recon_data, data.into_iter().map(Some).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_fit_custom_encoder_with_default_value_type_encodes_unknown_sentinel() {
+        // `V` defaults to `u64`, the same type `UnknownPolicy::Encode`'s
+        // sentinel already is, so a default-typed `Custom` encoder should
+        // push it just like `Ordinal` does instead of silently dropping it.
+        let data: Vec<String> = vec!["hello".to_string(), "world".to_string()];
+        let config: Config<String> = Config {
+            max_nclasses: None,
+            mapping_function: Some(|el: String| el.len() as u64),
+            unknown: UnknownPolicy::Encode(999),
+            ordering: Ordering::InsertionOrder,
+        };
+
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::CustomMapping));
+        enc.fit_custom(&data, &config);
+
+        let query = vec!["hello".to_string(), "unseen".to_string()];
+        let trans_data = enc.transform(&query, &config).expect("Encode policy never errors");
+        match &trans_data {
+            Transform::CustomMapping(values) => assert_eq!(values, &vec![5, 999]),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_fit_custom_encoder_with_non_u64_value_type_encodes_unknown_sentinel() {
+        // `transform_custom`'s sentinel is a genuine `V`, supplied by the
+        // caller directly, so a `Custom` encoder whose `V` isn't `u64` can
+        // still push it instead of falling back to dropping the value.
+        let data: Vec<String> = vec!["hello".to_string()];
+        let config: Config<String, i64> = Config {
+            max_nclasses: None,
+            mapping_function: Some(|_: String| -1),
+            unknown: UnknownPolicy::Encode(999),
+            ordering: Ordering::InsertionOrder,
+        };
+
+        let mut enc: Encoder<String, i64> = Encoder::new(Some(EncoderType::CustomMapping));
+        enc.fit(&data, &config);
+
+        let query = vec!["hello".to_string(), "unseen".to_string()];
+        let trans_data = enc.transform_custom(&query, &config).expect("Encode policy never errors");
+        match &trans_data {
+            Transform::CustomMapping(values) => assert_eq!(values, &vec![-1, 999]),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_custom_roundtrip_with_string_values() {
+        let data: Vec<String> = vec!["hello".to_string(), "world".to_string()];
+        let config: Config<String, String> = Config {
+            max_nclasses: None,
+            mapping_function: Some(|el| el.to_uppercase()),
+            unknown: UnknownPolicy::Error,
+            ordering: Ordering::InsertionOrder,
+        };
+        let mut enc: Encoder<String, String> = Encoder::new(Some(EncoderType::CustomMapping));
+        enc.fit(&data, &config);
+
+        let bytes = enc.encode();
+        let decoded: Encoder<String, String> = Encoder::decode(&bytes).expect("decode should succeed");
+
+        let trans_data = decoded.transform_custom(&data, &config).expect("no unseen categories");
+        match trans_data {
+            Transform::CustomMapping(values) => {
+                assert_eq!(values, vec!["HELLO".to_string(), "WORLD".to_string()]);
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_transform_unknown_policy_error() {
+        let data: Vec<String> = vec!["hello".to_string()];
+        let config = Config { max_nclasses: None, mapping_function: None, unknown: UnknownPolicy::Error, ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+        enc.fit(&data, &config);
+
+        let result = enc.transform(&vec!["unseen".to_string()], &config);
+        assert_eq!(result.unwrap_err(), EncodeError::UnknownCategory("unseen".to_string()));
+    }
+
+    #[test]
+    fn test_transform_unknown_policy_ignore() {
+        let data: Vec<String> = vec!["hello".to_string()];
+        let config = Config { max_nclasses: None, mapping_function: None, unknown: UnknownPolicy::Ignore, ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+        enc.fit(&data, &config);
+
+        let trans_data = enc.transform(&vec!["hello".to_string(), "unseen".to_string()], &config).unwrap();
+        assert_eq!(trans_data.len(), 1);
+    }
+
+    #[test]
+    fn test_transform_unknown_policy_encode_sentinel() {
+        let data: Vec<String> = vec!["hello".to_string()];
+        let config = Config { max_nclasses: None, mapping_function: None, unknown: UnknownPolicy::Encode(999), ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+        enc.fit(&data, &config);
+
+        let trans_data = enc.transform(&vec!["hello".to_string(), "unseen".to_string()], &config).unwrap();
+        match trans_data {
+            Transform::Ordinal(codes) => assert_eq!(codes, vec![0, 999]),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_fit_lexicographic_ordering_is_deterministic() {
+        let data: Vec<String> = vec!["world".to_string(), "hello".to_string(), "again".to_string()];
+        let config = Config {
+            max_nclasses: None,
+            mapping_function: None,
+            unknown: UnknownPolicy::Error,
+            ordering: Ordering::Lexicographic,
+        };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+        enc.fit(&data, &config);
+
+        match &enc {
+            Encoder::Ordinal(map, _) => {
+                assert_eq!(*map.get("again").unwrap(), 0);
+                assert_eq!(*map.get("hello").unwrap(), 1);
+                assert_eq!(*map.get("world").unwrap(), 2);
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_fit_frequency_ordering_breaks_ties_lexicographically() {
+        let data: Vec<String> = vec![
+            "world".to_string(), "world".to_string(),
+            "hello".to_string(),
+            "again".to_string(),
+        ];
+        let config = Config {
+            max_nclasses: None,
+            mapping_function: None,
+            unknown: UnknownPolicy::Error,
+            ordering: Ordering::Frequency,
+        };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+        enc.fit(&data, &config);
+
+        match &enc {
+            Encoder::Ordinal(map, _) => {
+                // "world" appears twice, so it gets code 0; "again" and
+                // "hello" tie on frequency and break the tie lexicographically
+                assert_eq!(*map.get("world").unwrap(), 0);
+                assert_eq!(*map.get("again").unwrap(), 1);
+                assert_eq!(*map.get("hello").unwrap(), 2);
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_partial_fit_ordinal_preserves_codes() {
+        let config = Config { max_nclasses: None, mapping_function: None, unknown: UnknownPolicy::Error, ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+
+        enc.partial_fit(&vec!["hello".to_string(), "world".to_string()], &config);
+        let hello_before = match &enc {
+            Encoder::Ordinal(map, _) => *map.get("hello").unwrap(),
+            _ => unreachable!(),
+        };
+
+        enc.partial_fit(&vec!["again".to_string(), "hello".to_string()], &config);
+        match &enc {
+            Encoder::Ordinal(map, _) => {
+                assert_eq!(*map.get("hello").unwrap(), hello_before);
+                assert_eq!(map.len(), 3);
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_partial_fit_one_hot_preserves_codes() {
+        let config = Config { max_nclasses: None, mapping_function: None, unknown: UnknownPolicy::Error, ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::OneHot));
+
+        enc.partial_fit(&vec!["hello".to_string(), "world".to_string()], &config);
+        let hello_before = match &enc {
+            Encoder::OneHot(map, _) => *map.get("hello").unwrap(),
+            _ => unreachable!(),
+        };
+
+        enc.partial_fit(&vec!["again".to_string()], &config);
+        match &enc {
+            Encoder::OneHot(map, _) => {
+                assert_eq!(*map.get("hello").unwrap(), hello_before);
+                assert_eq!(map.len(), 3);
+            },
+            _ => unreachable!(),
+        }
+        assert_eq!(enc.nclasses(), 3);
+    }
+
+    #[test]
+    fn test_encode_decode_ordinal_roundtrip() {
+        let data: Vec<String> = vec!["hello".to_string(), "world".to_string(), "again".to_string()];
+        let config = Config { max_nclasses: None, mapping_function: None, unknown: UnknownPolicy::Error, ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+        enc.fit(&data, &config);
+
+        let bytes = enc.encode();
+        let decoded: Encoder<String> = Encoder::decode(&bytes).expect("decode should succeed");
+
+        let before = enc.transform(&data, &config).expect("no unseen categories");
+        let after = decoded.transform(&data, &config).expect("no unseen categories");
+        assert_eq!(before.len(), after.len());
+        assert_eq!(decoded.nclasses(), enc.nclasses());
+    }
+
+    #[test]
+    fn test_encode_decode_one_hot_roundtrip() {
+        let data: Vec<String> = vec!["hello".to_string(), "world".to_string(), "again".to_string()];
+        let config = Config { max_nclasses: Some(10), mapping_function: None, unknown: UnknownPolicy::Error, ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::OneHot));
+        enc.fit(&data, &config);
+
+        let bytes = enc.encode();
+        let decoded: Encoder<String> = Encoder::decode(&bytes).expect("decode should succeed");
+
+        assert_eq!(decoded.nclasses(), enc.nclasses());
+    }
+
+    #[test]
+    fn test_inverse_transform_preserves_input_length() {
+        let data: Vec<String> = vec![
+            "hello".to_string(), "world".to_string(), "hello".to_string(), "again".to_string(),
+        ];
+        let config = Config { max_nclasses: None, mapping_function: None, unknown: UnknownPolicy::Error, ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+        enc.fit(&data, &config);
+
+        let trans_data = enc.transform(&data, &config).expect("no unseen categories");
+        let recon_data = enc.inverse_transform(&trans_data);
+        assert_eq!(recon_data, data.into_iter().map(Some).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_inverse_transform_collapsed_code_preserves_length() {
+        // max_nclasses collapses "again" onto the same code as "world",
+        // making the forward map non-injective; the reverse index should
+        // still return a 1:1-length result rather than panicking.
+        let data: Vec<String> = vec!["hello".to_string(), "world".to_string(), "again".to_string()];
+        let config = Config { max_nclasses: Some(2), mapping_function: None, unknown: UnknownPolicy::Error, ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+        enc.fit(&data, &config);
+
+        let trans_data = enc.transform(&data, &config).expect("no unseen categories");
+        let recon_data = enc.inverse_transform(&trans_data);
+        assert_eq!(recon_data.len(), data.len());
+    }
+
+    #[test]
+    fn test_inverse_transform_unknown_sentinel_code_returns_none() {
+        // `transform` with `UnknownPolicy::Encode` can emit a sentinel code
+        // that was never a key in the fitted forward map; `inverse_transform`
+        // must return `None` for it instead of panicking.
+        let data: Vec<String> = vec!["hello".to_string()];
+        let config = Config { max_nclasses: None, mapping_function: None, unknown: UnknownPolicy::Encode(999), ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+        enc.fit(&data, &config);
+
+        let query = vec!["hello".to_string(), "unseen".to_string()];
+        let trans_data = enc.transform(&query, &config).expect("Encode policy never errors");
+        match &trans_data {
+            Transform::Ordinal(values) => assert_eq!(values, &vec![0, 999]),
+            _ => unreachable!(),
+        }
+
+        let recon_data = enc.inverse_transform(&trans_data);
+        assert_eq!(recon_data, vec![Some("hello".to_string()), None]);
+    }
+
+    #[test]
+    fn test_decode_unknown_tag() {
+        let bytes = b"not-a-real-tag{0:}".to_vec();
+        let result: Result<Encoder<String>, DecodeError> = Encoder::decode(&bytes);
+        assert_eq!(result.unwrap_err(), DecodeError::UnknownTag);
+    }
+
+    #[test]
+    fn test_decode_truncated_input() {
+        let data: Vec<String> = vec!["hello".to_string()];
+        let config = Config { max_nclasses: None, mapping_function: None, unknown: UnknownPolicy::Error, ordering: Ordering::InsertionOrder };
+        let mut enc: Encoder<String> = Encoder::new(Some(EncoderType::Ordinal));
+        enc.fit(&data, &config);
+
+        let mut bytes = enc.encode();
+        bytes.truncate(bytes.len() - 2);
+        let result: Result<Encoder<String>, DecodeError> = Encoder::decode(&bytes);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_decode_huge_count_does_not_abort_on_allocation() {
+        let bytes = b"ordinal{18446744073709551000:".to_vec();
+        let result: Result<Encoder<String>, DecodeError> = Encoder::decode(&bytes);
+        assert!(result.is_err());
+    }
+}